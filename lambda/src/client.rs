@@ -0,0 +1,52 @@
+use crate::Error;
+use http::{Request, Response, Uri};
+use hyper::{client::HttpConnector, Body};
+use std::time::Duration;
+
+/// Builds the [`HttpConnector`] used by [`crate::run`]: keep-alive enabled, since a warm
+/// container reuses it for every invocation's long-poll `next` request and completion/error
+/// `POST`, and with no TLS, since the runtime API is only ever reached over the loopback
+/// interface.
+pub(crate) fn default_connector() -> HttpConnector {
+    let mut connector = HttpConnector::new();
+    connector.set_keepalive(Some(Duration::from_secs(60)));
+    connector
+}
+
+/// A thin wrapper around a [`hyper::Client`] that resolves request paths
+/// against the runtime API's base URI.
+#[derive(Clone)]
+pub(crate) struct Client {
+    base: Uri,
+    client: hyper::Client<HttpConnector>,
+}
+
+impl Client {
+    /// Creates a new client pointed at `base` using the provided hyper client. The same
+    /// `hyper::Client` (and its connection pool) is reused for every request `call` makes, so
+    /// the long-poll `next` request and the completion/error `POST` share a connection instead
+    /// of each opening a fresh one.
+    pub(crate) fn with(base: Uri, client: hyper::Client<HttpConnector>) -> Self {
+        Client { base, client }
+    }
+
+    pub(crate) async fn call(&self, req: Request<Body>) -> Result<Response<Body>, Error> {
+        let (mut parts, body) = req.into_parts();
+        let path = parts.uri.path_and_query().cloned();
+        let mut builder = Uri::builder();
+        if let Some(scheme) = self.base.scheme() {
+            builder = builder.scheme(scheme.clone());
+        }
+        if let Some(authority) = self.base.authority() {
+            builder = builder.authority(authority.clone());
+        }
+        if let Some(path) = path {
+            builder = builder.path_and_query(path);
+        }
+        parts.uri = builder.build()?;
+
+        let req = Request::from_parts(parts, body);
+        let res = self.client.request(req).await?;
+        Ok(res)
+    }
+}
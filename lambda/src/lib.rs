@@ -16,6 +16,28 @@
 //! 2. A type that conforms to the [`Handler`] trait. This type can then be passed
 //!    to the the `lamedh_runtime::run` function, which launches and runs the Lambda runtime.
 //!
+//! For cases that need middleware around the handler itself (retries, timeouts, concurrency
+//! limits, logging, ...), build a [`tower::Service<LambdaEvent<A>>`] (from [`tower::service_fn`]
+//! or by wrapping a [`Handler`] in [`HandlerService`]) and drive it with [`Runtime::new`], which
+//! accepts any number of [`tower::Layer`]s wrapping the inner service via [`Runtime::layer`]
+//! before [`Runtime::run`] starts the poll loop.
+//!
+//! Handlers with large or long-lived responses can avoid buffering the whole result in memory
+//! by returning a [`StreamResponse`] and running via [`run_streaming`] instead of [`run`].
+//!
+//! Every invocation is wrapped in a [`tracing`] span carrying the request ID, X-Ray trace ID,
+//! function ARN, and whether this was the process's cold start. Call
+//! [`init_tracing_subscriber`] at the start of `main` to get those spans logged as the JSON
+//! CloudWatch expects.
+//!
+//! For local development, enabling the `dotenv` feature loads a `.env` file into the process
+//! environment before [`Config::from_env`] runs, and [`run_local`] invokes a handler once
+//! against a JSON event read from a file or stdin instead of polling the runtime API.
+//!
+//! The [`events`] module ships typed models for common AWS event sources (S3, SQS, ...), so
+//! handlers can accept them directly instead of hand-written structs; deserialization failures
+//! surface as a [`PayloadError`] naming the JSON path that didn't match.
+//!
 //! An asynchronous function annotated with the `#[lambda]` attribute must
 //! accept an argument of type `A` which implements [`serde::Deserialize`], a [`lambda::Context`] and
 //! return a `Result<B, E>`, where `B` implements [`serde::Serializable`]. `E` is
@@ -37,28 +59,28 @@
 //! [`lambda`]: attr.lambda.html
 //! [`#[tokio::main]`]: https://docs.rs/tokio/0.2.21/tokio/attr.main.html
 //! [Tokio]: https://docs.rs/tokio/
-pub use crate::types::Context;
+pub use crate::types::{events, Context, LambdaEvent, PayloadError, StreamResponse};
 use client::Client;
 use futures_core::stream::Stream;
 use futures_util::stream::StreamExt;
 pub use lamedh_attributes::lambda;
+pub use runtime::{HandlerService, Runtime};
 use serde::{Deserialize, Serialize};
-use std::{
-    convert::{TryFrom, TryInto},
-    env, fmt,
-    future::Future,
-};
+use std::{convert::TryInto, env, fmt, future::Future};
 use tracing::trace;
 
 mod client;
+mod local;
 mod requests;
+mod runtime;
 #[cfg(test)]
 mod simulated;
 /// Types available to a Lambda function.
 mod types;
 
-use requests::{EventCompletionRequest, EventErrorRequest, IntoRequest, NextEventRequest};
-use types::Diagnostic;
+pub use local::run_local;
+
+use requests::{IntoRequest, NextEventRequest};
 
 static DEFAULT_LOG_GROUP: &str = "/aws/lambda/Functions";
 static DEFAULT_LOG_STREAM: &str = "$LATEST";
@@ -86,6 +108,9 @@ pub struct Config {
 impl Config {
     /// Attempts to read configuration from environment variables.
     pub fn from_env() -> Result<Self, Error> {
+        #[cfg(feature = "dotenv")]
+        load_dotenv();
+
         let conf = Config {
             endpoint: env::var("AWS_LAMBDA_RUNTIME_API")?,
             function_name: env::var("AWS_LAMBDA_FUNCTION_NAME")?,
@@ -98,6 +123,32 @@ impl Config {
     }
 }
 
+/// Loads a `.env` file from the current directory into the process environment, so local
+/// development doesn't need the real Lambda runtime env vars to be set by hand. Lines are
+/// `key=value` pairs; blank lines and lines starting with `#` are ignored. Variables already
+/// set in the environment are left untouched, and a missing file is treated as empty rather
+/// than an error.
+#[cfg(feature = "dotenv")]
+fn load_dotenv() {
+    let contents = match std::fs::read_to_string(".env") {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim();
+            if env::var(key).is_err() {
+                env::set_var(key, value.trim());
+            }
+        }
+    }
+}
+
 /// A trait describing an asynchronous function `A` to `B`.
 pub trait Handler<A, B> {
     /// Errors returned by this handler.
@@ -156,6 +207,29 @@ where
 /// }
 /// ```
 pub async fn run<A, B, F>(handler: F) -> Result<(), Error>
+where
+    F: Handler<A, B>,
+    <F as Handler<A, B>>::Error: fmt::Debug,
+    A: for<'de> Deserialize<'de>,
+    B: Serialize,
+{
+    let hyper_client = hyper::Client::builder().build(client::default_connector());
+    run_with_client(handler, hyper_client).await
+}
+
+/// A lower-level alternative to [`run`] for advanced users who want to supply their own
+/// [`hyper::Client`], for instance to tune connection-pool settings, rather than the
+/// keep-alive default [`run`] builds. The client is reused for every request the poll loop
+/// makes: the long-poll `next` request and the completion/error `POST` share one connection
+/// instead of each opening a fresh one per iteration.
+///
+/// Like [`run`], this drives `handler` with no [`tower::Layer`]s applied. To wrap the poll loop
+/// in middleware, build a [`Runtime`] directly with [`Runtime::new`] (wrapping `handler` in
+/// [`HandlerService`]), stack layers with [`Runtime::layer`], and call [`Runtime::run`] instead.
+pub async fn run_with_client<A, B, F>(
+    handler: F,
+    hyper_client: hyper::Client<hyper::client::HttpConnector>,
+) -> Result<(), Error>
 where
     F: Handler<A, B>,
     <F as Handler<A, B>>::Error: fmt::Debug,
@@ -163,14 +237,11 @@ where
     B: Serialize,
 {
     trace!("Loading config from env");
-    let mut handler = handler;
     let config = Config::from_env()?;
     let uri = config.endpoint.try_into().expect("Unable to convert to URL");
-    let client = Client::with(uri, hyper::Client::new());
-    let incoming = incoming(&client);
-    run_inner(&client, incoming, &mut handler).await?;
-
-    Ok(())
+    let client = Client::with(uri, hyper_client);
+    let runtime = Runtime::from_parts(client, HandlerService::new(handler));
+    runtime.run::<A, B>().await
 }
 
 /// Runs the lambda function almost entirely in-memory. This is meant for testing.
@@ -181,16 +252,35 @@ where
     A: for<'de> Deserialize<'de>,
     B: Serialize,
 {
-    let mut handler = handler;
     let uri = url.try_into().expect("Unable to convert to URL");
-    let client = Client::with(uri, hyper::Client::new());
-    let incoming = incoming(&client).take(1);
-    run_inner(&client, incoming, &mut handler).await?;
+    let client = Client::with(uri, hyper::Client::builder().build(client::default_connector()));
+    let runtime = Runtime::from_parts(client.clone(), HandlerService::new(handler));
+    runtime::run_inner(&client, incoming(&client).take(1), runtime.service).await
+}
 
-    Ok(())
+/// Like [`run`], but for handlers whose response is produced incrementally. The handler
+/// returns a [`StreamResponse`] wrapping a `Stream` of byte chunks (`Result<bytes::Bytes, Error>`),
+/// each of which is forwarded to the runtime API's streaming response endpoint as soon as it
+/// is produced, instead of being buffered into a single body. This suits large or long-lived
+/// responses, such as SSE or other progressive payloads, without hitting the buffered-response
+/// size limit.
+pub async fn run_streaming<A, S, F>(handler: F) -> Result<(), Error>
+where
+    F: Handler<A, StreamResponse<S>>,
+    <F as Handler<A, StreamResponse<S>>>::Error: fmt::Debug,
+    A: for<'de> Deserialize<'de>,
+    S: Stream<Item = Result<bytes::Bytes, Error>> + Send + 'static,
+{
+    trace!("Loading config from env");
+    let mut handler = handler;
+    let config = Config::from_env()?;
+    let uri = config.endpoint.try_into().expect("Unable to convert to URL");
+    let client = Client::with(uri, hyper::Client::builder().build(client::default_connector()));
+    let incoming = incoming(&client);
+    runtime::run_streaming_inner(&client, incoming, &mut handler).await
 }
 
-fn incoming(client: &Client) -> impl Stream<Item = Result<http::Response<hyper::Body>, Error>> + '_ {
+pub(crate) fn incoming(client: &Client) -> impl Stream<Item = Result<http::Response<hyper::Body>, Error>> + '_ {
     async_stream::stream! {
         loop {
             let req = NextEventRequest.into_req().expect("Unable to construct request");
@@ -200,48 +290,20 @@ fn incoming(client: &Client) -> impl Stream<Item = Result<http::Response<hyper::
     }
 }
 
-async fn run_inner<A, B, F>(
-    client: &Client,
-    incoming: impl Stream<Item = Result<http::Response<hyper::Body>, Error>>,
-    handler: &mut F,
-) -> Result<(), Error>
-where
-    F: Handler<A, B>,
-    <F as Handler<A, B>>::Error: fmt::Debug,
-    A: for<'de> Deserialize<'de>,
-    B: Serialize,
-{
-    tokio::pin!(incoming);
-
-    while let Some(event) = incoming.next().await {
-        let event = event?;
-        let (parts, body) = event.into_parts();
-
-        let mut ctx: Context = Context::try_from(parts.headers)?;
-        ctx.env_config = Config::from_env()?;
-        let body = hyper::body::to_bytes(body).await?;
-        let body = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(&body))?;
-
-        let request_id = &ctx.request_id.clone();
-        let f = handler.call(body, ctx);
-
-        let req = match f.await {
-            Ok(res) => EventCompletionRequest { request_id, body: res }.into_req()?,
-            Err(e) => EventErrorRequest {
-                request_id,
-                diagnostic: Diagnostic {
-                    error_message: format!("{:?}", e),
-                    error_type: type_name_of_val(e).to_owned(),
-                },
-            }
-            .into_req()?,
-        };
-        client.call(req).await?;
-    }
-
-    Ok(())
+pub(crate) fn type_name_of_val<T>(_: T) -> &'static str {
+    std::any::type_name::<T>()
 }
 
-fn type_name_of_val<T>(_: T) -> &'static str {
-    std::any::type_name::<T>()
+/// Configures a global `tracing-subscriber` that emits JSON-formatted logs, which CloudWatch
+/// Logs parses well, at the level given by the `RUST_LOG` environment variable (defaulting to
+/// `info` if it is unset or invalid). Call this once at the start of `main`, before [`run`],
+/// to get per-invocation spans (see [`Context`]) without hand-rolling subscriber setup.
+pub fn init_tracing_subscriber() {
+    tracing_subscriber::fmt()
+        .json()
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_env("RUST_LOG")
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .init();
 }
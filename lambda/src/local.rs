@@ -0,0 +1,35 @@
+//! Local invocation support, for exercising a handler without deploying it.
+use crate::{Context, Error, Handler};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{fmt, fs, io::Read};
+
+/// Reads a single JSON event from `path` (or stdin, if `path` is `None`), invokes `handler`
+/// once with a synthesized [`Context`], and prints the serialized result to stdout. This
+/// mirrors the common workflow of testing an S3/SQS-triggered function on a laptop without
+/// deploying it.
+pub async fn run_local<A, B, F>(handler: F, path: Option<&str>) -> Result<(), Error>
+where
+    F: Handler<A, B>,
+    <F as Handler<A, B>>::Error: fmt::Debug,
+    A: DeserializeOwned,
+    B: Serialize,
+{
+    let mut handler = handler;
+    let input = match path {
+        Some(path) => fs::read_to_string(path)?,
+        None => {
+            let mut buf = String::new();
+            std::io::stdin().read_to_string(&mut buf)?;
+            buf
+        }
+    };
+
+    let payload = crate::types::deserialize_payload(input.as_bytes())?;
+    let res = match handler.call(payload, Context::local()).await {
+        Ok(res) => res,
+        Err(e) => return Err(format!("{:?}", e).into()),
+    };
+    println!("{}", serde_json::to_string(&res)?);
+
+    Ok(())
+}
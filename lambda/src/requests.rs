@@ -0,0 +1,97 @@
+use crate::{types::Diagnostic, Error};
+use bytes::Bytes;
+use futures_core::stream::Stream;
+use futures_util::stream::{self, StreamExt};
+use http::{Method, Request, Uri};
+use hyper::Body;
+use serde::Serialize;
+
+/// The 8 NUL bytes the runtime API's HTTP-integration response mode expects between the JSON
+/// metadata prelude and the start of the response body.
+const PRELUDE_SEPARATOR: [u8; 8] = [0; 8];
+
+pub(crate) trait IntoRequest {
+    fn into_req(self) -> Result<Request<Body>, Error>;
+}
+
+/// Long-polls the next invocation from the runtime API.
+pub(crate) struct NextEventRequest;
+
+impl IntoRequest for NextEventRequest {
+    fn into_req(self) -> Result<Request<Body>, Error> {
+        let uri = Uri::from_static("/2018-06-01/runtime/invocation/next");
+        let req = Request::builder().method(Method::GET).uri(uri).body(Body::empty())?;
+        Ok(req)
+    }
+}
+
+/// Reports a successful invocation result back to the runtime API.
+pub(crate) struct EventCompletionRequest<'a, B> {
+    pub(crate) request_id: &'a str,
+    pub(crate) body: B,
+}
+
+impl<'a, B: Serialize> IntoRequest for EventCompletionRequest<'a, B> {
+    fn into_req(self) -> Result<Request<Body>, Error> {
+        let uri = format!("/2018-06-01/runtime/invocation/{}/response", self.request_id);
+        let body = serde_json::to_vec(&self.body)?;
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .body(Body::from(body))?;
+        Ok(req)
+    }
+}
+
+/// Reports a streamed invocation result back to the runtime API using the runtime API's HTTP
+/// response-streaming mode: a content-type signaling a streamed payload, an initial JSON
+/// metadata prelude (terminated by the 8-byte NUL separator the runtime API expects), and then
+/// each chunk of `body` written as it is produced instead of one buffered response.
+pub(crate) struct StreamingEventCompletionRequest<'a, S> {
+    pub(crate) request_id: &'a str,
+    pub(crate) body: S,
+}
+
+impl<'a, S> IntoRequest for StreamingEventCompletionRequest<'a, S>
+where
+    S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+{
+    fn into_req(self) -> Result<Request<Body>, Error> {
+        let uri = format!("/2018-06-01/runtime/invocation/{}/response", self.request_id);
+
+        let prelude = serde_json::to_vec(&serde_json::json!({ "statusCode": 200 }))
+            .expect("metadata prelude is always valid JSON");
+        let prelude = stream::iter(vec![
+            Ok(Bytes::from(prelude)),
+            Ok(Bytes::from_static(&PRELUDE_SEPARATOR)),
+        ]);
+        let body = prelude.chain(self.body);
+
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("lambda-runtime-function-response-mode", "streaming")
+            .header("content-type", "application/vnd.awslambda.http-integration-response")
+            .body(Body::wrap_stream(body))?;
+        Ok(req)
+    }
+}
+
+/// Reports a failed invocation back to the runtime API.
+pub(crate) struct EventErrorRequest<'a> {
+    pub(crate) request_id: &'a str,
+    pub(crate) diagnostic: Diagnostic,
+}
+
+impl<'a> IntoRequest for EventErrorRequest<'a> {
+    fn into_req(self) -> Result<Request<Body>, Error> {
+        let uri = format!("/2018-06-01/runtime/invocation/{}/error", self.request_id);
+        let body = serde_json::to_vec(&self.diagnostic)?;
+        let req = Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("lambda-runtime-function-error-type", "Runtime.UnhandledError")
+            .body(Body::from(body))?;
+        Ok(req)
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{
+    client::{self, Client},
+    requests::{EventCompletionRequest, EventErrorRequest, IntoRequest, StreamingEventCompletionRequest},
+    type_name_of_val,
+    types::{self, Context, Diagnostic, LambdaEvent, StreamResponse},
+    Config, Error, Handler,
+};
+use bytes::Bytes;
+use futures_core::stream::Stream;
+use futures_util::{future::FutureExt, stream::StreamExt};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    any::Any,
+    convert::{TryFrom, TryInto},
+    fmt,
+    future::poll_fn,
+    panic::AssertUnwindSafe,
+    sync::atomic::{AtomicBool, Ordering},
+    task::{Context as TaskContext, Poll},
+    time::Instant,
+};
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+/// Tracks whether the current process has started an invocation yet, so the first
+/// invocation's span can be marked as a cold start.
+static COLD_START: AtomicBool = AtomicBool::new(true);
+
+fn invocation_span(ctx: &Context) -> tracing::Span {
+    let cold_start = COLD_START.swap(false, Ordering::SeqCst);
+    tracing::info_span!(
+        "Lambda runtime invoke",
+        requestId = %ctx.request_id,
+        xray_trace_id = %ctx.xray_trace_id.as_deref().unwrap_or_default(),
+        function_arn = %ctx.invoked_function_arn,
+        cold_start,
+    )
+}
+
+/// Adapts a [`Handler`] into a [`tower::Service`] so it can be driven by a
+/// [`Runtime`] and wrapped in [`tower::Layer`]s like any other service.
+///
+/// [`Handler`]: trait.Handler.html
+/// [`Runtime`]: struct.Runtime.html
+#[derive(Clone, Debug)]
+pub struct HandlerService<H> {
+    handler: H,
+}
+
+impl<H> HandlerService<H> {
+    /// Wraps `handler` as a [`tower::Service<LambdaEvent<A>>`], so it can be passed to
+    /// [`Runtime::new`] and layered like any other service.
+    pub fn new(handler: H) -> Self {
+        HandlerService { handler }
+    }
+}
+
+impl<H, A, B> Service<LambdaEvent<A>> for HandlerService<H>
+where
+    H: Handler<A, B>,
+{
+    type Response = B;
+    type Error = H::Error;
+    type Future = H::Fut;
+
+    fn poll_ready(&mut self, _cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, event: LambdaEvent<A>) -> Self::Future {
+        self.handler.call(event.payload, event.context)
+    }
+}
+
+/// Drives a [`tower::Service<LambdaEvent<A>>`] against the Lambda [runtime
+/// API](https://docs.aws.amazon.com/lambda/latest/dg/runtimes-api.html), polling for
+/// events and reporting their results.
+///
+/// A `Runtime` is built around an inner service (for instance, one produced by
+/// [`tower::service_fn`] or the [`Handler`]→[`Service`] adapter used by [`run`]) and can be
+/// wrapped in any number of [`tower::Layer`]s via [`Runtime::layer`] before it is run, which
+/// makes cross-cutting concerns like timeouts, retries, or concurrency limits composable
+/// instead of baked into the handler itself.
+///
+/// [`Handler`]: trait.Handler.html
+/// [`Service`]: tower::Service
+/// [`run`]: fn.run.html
+pub struct Runtime<S> {
+    client: Client,
+    pub(crate) service: S,
+}
+
+impl<S> Runtime<S> {
+    /// Builds a `Runtime` around `service`, using a client configured from the process
+    /// environment (the same `AWS_LAMBDA_RUNTIME_API` endpoint and keep-alive connector
+    /// [`crate::run`] uses). This is the entry point for driving a `tower::Service<LambdaEvent<A>>`
+    /// built from [`tower::service_fn`] or [`HandlerService`] directly, so it can be wrapped in
+    /// [`tower::Layer`]s via [`Runtime::layer`]/[`Runtime::with_layer`] before [`Runtime::run`]
+    /// starts the poll loop.
+    pub fn new(service: S) -> Result<Self, Error> {
+        let config = Config::from_env()?;
+        let uri = config.endpoint.try_into()?;
+        let client = Client::with(uri, hyper::Client::builder().build(client::default_connector()));
+        Ok(Runtime::from_parts(client, service))
+    }
+
+    pub(crate) fn from_parts(client: Client, service: S) -> Self {
+        Runtime { client, service }
+    }
+
+    /// Wraps the runtime's inner service in `layer`, so every invocation passes through the
+    /// resulting middleware before reaching the handler.
+    pub fn layer<L>(self, layer: L) -> Runtime<L::Service>
+    where
+        L: Layer<S>,
+    {
+        Runtime {
+            client: self.client,
+            service: layer.layer(self.service),
+        }
+    }
+
+    /// An alias for [`Runtime::layer`], useful when stacking several layers in sequence, e.g.
+    /// `runtime.with_layer(a).with_layer(b)`.
+    pub fn with_layer<L>(self, layer: L) -> Runtime<L::Service>
+    where
+        L: Layer<S>,
+    {
+        self.layer(layer)
+    }
+
+    /// Starts the runtime's poll loop, dispatching every invocation through the (possibly
+    /// layered) inner service.
+    pub async fn run<A, B>(self) -> Result<(), Error>
+    where
+        S: Service<LambdaEvent<A>, Response = B>,
+        S::Error: fmt::Debug,
+        A: DeserializeOwned,
+        B: Serialize,
+    {
+        let incoming = crate::incoming(&self.client);
+        run_inner(&self.client, incoming, self.service).await
+    }
+}
+
+pub(crate) async fn run_inner<A, B, S>(
+    client: &Client,
+    incoming: impl Stream<Item = Result<http::Response<hyper::Body>, Error>>,
+    mut service: S,
+) -> Result<(), Error>
+where
+    S: Service<LambdaEvent<A>, Response = B>,
+    S::Error: fmt::Debug,
+    A: DeserializeOwned,
+    B: Serialize,
+{
+    tokio::pin!(incoming);
+
+    while let Some(event) = incoming.next().await {
+        let event = event?;
+        let (parts, body) = event.into_parts();
+
+        let mut ctx: Context = Context::try_from(parts.headers)?;
+        ctx.env_config = Config::from_env()?;
+        let body = hyper::body::to_bytes(body).await?;
+        let payload = types::deserialize_payload(&body)?;
+
+        let request_id = ctx.request_id.clone();
+        let span = invocation_span(&ctx);
+        let start = Instant::now();
+        let event = LambdaEvent { payload, context: ctx };
+
+        let req = match poll_fn(|cx| service.poll_ready(cx)).await {
+            Ok(()) => match AssertUnwindSafe(service.call(event))
+                .catch_unwind()
+                .instrument(span.clone())
+                .await
+            {
+                Ok(Ok(res)) => EventCompletionRequest {
+                    request_id: &request_id,
+                    body: res,
+                }
+                .into_req()?,
+                Ok(Err(e)) => error_req(&request_id, e)?,
+                Err(panic) => panic_req(&request_id, panic)?,
+            },
+            Err(e) => error_req(&request_id, e)?,
+        };
+        tracing::info!(parent: &span, elapsed_ms = start.elapsed().as_millis() as u64, "invocation complete");
+        client.call(req).await?;
+    }
+
+    Ok(())
+}
+
+/// Drives the poll loop for [`crate::run_streaming`], forwarding each chunk of the handler's
+/// response stream to the runtime API as it is produced instead of buffering the whole body.
+pub(crate) async fn run_streaming_inner<A, S, F>(
+    client: &Client,
+    incoming: impl Stream<Item = Result<http::Response<hyper::Body>, Error>>,
+    handler: &mut F,
+) -> Result<(), Error>
+where
+    F: Handler<A, StreamResponse<S>>,
+    F::Error: fmt::Debug,
+    A: DeserializeOwned,
+    S: Stream<Item = Result<Bytes, Error>> + Send + 'static,
+{
+    tokio::pin!(incoming);
+
+    while let Some(event) = incoming.next().await {
+        let event = event?;
+        let (parts, body) = event.into_parts();
+
+        let mut ctx: Context = Context::try_from(parts.headers)?;
+        ctx.env_config = Config::from_env()?;
+        let body = hyper::body::to_bytes(body).await?;
+        let payload = types::deserialize_payload(&body)?;
+
+        let request_id = ctx.request_id.clone();
+        let span = invocation_span(&ctx);
+        let start = Instant::now();
+        let req = match AssertUnwindSafe(handler.call(payload, ctx))
+            .catch_unwind()
+            .instrument(span.clone())
+            .await
+        {
+            Ok(Ok(StreamResponse(stream))) => StreamingEventCompletionRequest {
+                request_id: &request_id,
+                body: stream,
+            }
+            .into_req()?,
+            Ok(Err(e)) => error_req(&request_id, e)?,
+            Err(panic) => panic_req(&request_id, panic)?,
+        };
+        tracing::info!(parent: &span, elapsed_ms = start.elapsed().as_millis() as u64, "invocation complete");
+        client.call(req).await?;
+    }
+
+    Ok(())
+}
+
+fn error_req<E: fmt::Debug>(request_id: &str, err: E) -> Result<http::Request<hyper::Body>, Error> {
+    EventErrorRequest {
+        request_id,
+        diagnostic: Diagnostic {
+            error_message: format!("{:?}", err),
+            error_type: type_name_of_val(err).to_owned(),
+        },
+    }
+    .into_req()
+}
+
+/// Converts a caught handler panic into an `EventErrorRequest` instead of letting it unwind
+/// through the poll loop, so a single bad invocation doesn't take down a warm runtime.
+fn panic_req(request_id: &str, panic: Box<dyn Any + Send>) -> Result<http::Request<hyper::Body>, Error> {
+    let error_message = panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "lambda handler panicked".to_owned());
+
+    EventErrorRequest {
+        request_id,
+        diagnostic: Diagnostic {
+            error_message,
+            error_type: "PanicError".to_owned(),
+        },
+    }
+    .into_req()
+}
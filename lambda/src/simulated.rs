@@ -0,0 +1,184 @@
+use crate::{
+    client::{self, Client},
+    handler_fn, run_simulated,
+    runtime::{self, HandlerService, Runtime},
+    Context, Error, LambdaEvent, StreamResponse,
+};
+use bytes::Bytes;
+use futures_util::stream::{self, StreamExt};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server,
+};
+use std::{
+    convert::{Infallible, TryInto},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context as TaskContext, Poll},
+};
+use tower::{Layer, Service};
+
+/// Responds to a single `next` long-poll with a fixed, minimal event, standing in for the
+/// Lambda runtime API in tests.
+async fn next_event(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let res = match req.uri().path() {
+        "/2018-06-01/runtime/invocation/next" => Response::builder()
+            .header("lambda-runtime-aws-request-id", "156cb537-e2de-11e8-9b34-d36013741fb9")
+            .body(Body::from("{}"))
+            .unwrap(),
+        _ => Response::new(Body::empty()),
+    };
+    Ok(res)
+}
+
+/// Sets the environment variables `Config::from_env` requires, since the poll loop reads them
+/// for every event's `Context`. Every test sets the same values.
+fn set_lambda_env() {
+    std::env::set_var("AWS_LAMBDA_RUNTIME_API", "127.0.0.1:9001");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_NAME", "test-function");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_MEMORY_SIZE", "128");
+    std::env::set_var("AWS_LAMBDA_FUNCTION_VERSION", "$LATEST");
+}
+
+#[tokio::test]
+async fn runs_a_single_simulated_invocation() -> Result<(), Error> {
+    set_lambda_env();
+    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(next_event)) });
+    let server = Server::bind(&"127.0.0.1:0".parse()?).serve(make_svc);
+    let url = format!("http://{}", server.local_addr());
+    let server = tokio::spawn(server);
+
+    let func = handler_fn(|event: serde_json::Value, _: Context| async move { Ok::<_, Error>(event) });
+    run_simulated(func, &url).await?;
+
+    server.abort();
+    Ok(())
+}
+
+/// A [`tower::Layer`] that counts how many times the wrapped service is called, so a test can
+/// assert the layer actually ran in the poll loop instead of being bypassed.
+#[derive(Clone)]
+struct CountingLayer(Arc<AtomicUsize>);
+
+struct CountingService<S> {
+    inner: S,
+    calls: Arc<AtomicUsize>,
+}
+
+impl<S> Layer<S> for CountingLayer {
+    type Service = CountingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountingService {
+            inner,
+            calls: self.0.clone(),
+        }
+    }
+}
+
+impl<S, A> Service<LambdaEvent<A>> for CountingService<S>
+where
+    S: Service<LambdaEvent<A>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut TaskContext<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: LambdaEvent<A>) -> Self::Future {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.inner.call(req)
+    }
+}
+
+#[tokio::test]
+async fn runs_a_layered_service_through_the_poll_loop() -> Result<(), Error> {
+    set_lambda_env();
+    let make_svc = make_service_fn(|_conn| async { Ok::<_, Infallible>(service_fn(next_event)) });
+    let server = Server::bind(&"127.0.0.1:0".parse()?).serve(make_svc);
+    let url = format!("http://{}", server.local_addr());
+    let server = tokio::spawn(server);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let func = handler_fn(|event: serde_json::Value, _: Context| async move { Ok::<_, Error>(event) });
+    let client = Client::with(
+        url.as_str().try_into()?,
+        hyper::Client::builder().build(client::default_connector()),
+    );
+    let runtime = Runtime::from_parts(client.clone(), HandlerService::new(func)).layer(CountingLayer(calls.clone()));
+
+    runtime::run_inner(&client, crate::incoming(&client).take(1), runtime.service).await?;
+
+    server.abort();
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    Ok(())
+}
+
+/// Responds to a `next` long-poll like [`next_event`], and to a streamed completion `POST` by
+/// capturing the raw body bytes the runtime received, so a test can assert on the wire format.
+async fn streaming_stub(
+    req: Request<Body>,
+    captured: Arc<Mutex<Option<Vec<u8>>>>,
+) -> Result<Response<Body>, Infallible> {
+    let res = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/2018-06-01/runtime/invocation/next") => Response::builder()
+            .header("lambda-runtime-aws-request-id", "156cb537-e2de-11e8-9b34-d36013741fb9")
+            .body(Body::from("{}"))
+            .unwrap(),
+        (&Method::POST, path) if path.ends_with("/response") => {
+            let body = hyper::body::to_bytes(req.into_body()).await.unwrap_or_default();
+            *captured.lock().unwrap() = Some(body.to_vec());
+            Response::new(Body::empty())
+        }
+        _ => Response::new(Body::empty()),
+    };
+    Ok(res)
+}
+
+#[tokio::test]
+async fn streaming_response_begins_with_the_metadata_prelude() -> Result<(), Error> {
+    set_lambda_env();
+    let captured: Arc<Mutex<Option<Vec<u8>>>> = Arc::new(Mutex::new(None));
+    let captured_for_svc = captured.clone();
+    let make_svc = make_service_fn(move |_conn| {
+        let captured = captured_for_svc.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| streaming_stub(req, captured.clone()))) }
+    });
+    let server = Server::bind(&"127.0.0.1:0".parse()?).serve(make_svc);
+    let url = format!("http://{}", server.local_addr());
+    let server = tokio::spawn(server);
+
+    let client = Client::with(
+        url.as_str().try_into()?,
+        hyper::Client::builder().build(client::default_connector()),
+    );
+    let incoming = crate::incoming(&client).take(1);
+
+    let mut handler = handler_fn(|_event: serde_json::Value, _: Context| async move {
+        let chunks = stream::iter(vec![
+            Ok::<_, Error>(Bytes::from_static(b"hello")),
+            Ok(Bytes::from_static(b" world")),
+        ]);
+        Ok::<_, Error>(StreamResponse(chunks))
+    });
+    runtime::run_streaming_inner(&client, incoming, &mut handler).await?;
+
+    server.abort();
+
+    let body = captured
+        .lock()
+        .unwrap()
+        .take()
+        .expect("runtime API should have received a response POST");
+    let mut expected = serde_json::to_vec(&serde_json::json!({ "statusCode": 200 }))?;
+    expected.extend_from_slice(&[0u8; 8]);
+    expected.extend_from_slice(b"hello world");
+    assert_eq!(body, expected);
+
+    Ok(())
+}
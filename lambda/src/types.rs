@@ -0,0 +1,128 @@
+//! Types available to a Lambda function.
+use crate::{Config, Error};
+use http::HeaderMap;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{convert::TryFrom, fmt};
+
+pub mod events;
+
+const AWS_REQUEST_ID: &str = "lambda-runtime-aws-request-id";
+const AWS_XRAY_TRACE_ID: &str = "lambda-runtime-trace-id";
+const AWS_FUNCTION_ARN: &str = "lambda-runtime-invoked-function-arn";
+const AWS_DEADLINE_MS: &str = "lambda-runtime-deadline-ms";
+
+/// The Lambda function execution context, made available to a handler for
+/// every invocation. Values are derived from the headers on the invocation
+/// request returned by the [runtime
+/// API](https://docs.aws.amazon.com/lambda/latest/dg/runtimes-api.html) and
+/// from the process environment.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Context {
+    /// The AWS request ID generated by the Lambda service for this invocation.
+    pub request_id: String,
+    /// The X-Ray trace ID for the current invocation.
+    pub xray_trace_id: Option<String>,
+    /// The ARN of the Lambda function, version, or alias that is executing.
+    pub invoked_function_arn: String,
+    /// The execution deadline for the current invocation in milliseconds, as given by the
+    /// `lambda-runtime-deadline-ms` header (`0` if the header is missing or unparsable).
+    pub deadline: u64,
+    /// Configuration derived from the process environment.
+    pub env_config: Config,
+}
+
+impl TryFrom<HeaderMap> for Context {
+    type Error = Error;
+
+    fn try_from(headers: HeaderMap) -> Result<Self, Self::Error> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .map(|v| v.to_str().unwrap_or_default().to_owned())
+                .unwrap_or_default()
+        };
+
+        Ok(Context {
+            request_id: header(AWS_REQUEST_ID),
+            xray_trace_id: headers.get(AWS_XRAY_TRACE_ID).map(|_| header(AWS_XRAY_TRACE_ID)),
+            invoked_function_arn: header(AWS_FUNCTION_ARN),
+            deadline: header(AWS_DEADLINE_MS).parse().unwrap_or_default(),
+            env_config: Config::default(),
+        })
+    }
+}
+
+impl Context {
+    /// Builds a `Context` with placeholder values, for invoking a handler outside of the
+    /// Lambda runtime. Used by [`crate::run_local`].
+    pub(crate) fn local() -> Self {
+        Context {
+            request_id: "local-invoke".to_owned(),
+            xray_trace_id: None,
+            invoked_function_arn: "arn:aws:lambda:local:000000000000:function:local".to_owned(),
+            deadline: 0,
+            env_config: Config::default(),
+        }
+    }
+}
+
+/// An incoming Lambda invocation, bundling the deserialized event payload together with
+/// its [`Context`]. This is the request type accepted by a [`tower::Service`]-based
+/// handler, as opposed to the `(payload, Context)` pair taken by [`crate::Handler`].
+#[derive(Debug, Clone)]
+pub struct LambdaEvent<A> {
+    /// The event payload, deserialized from the invocation's request body.
+    pub payload: A,
+    /// The invocation's execution context.
+    pub context: Context,
+}
+
+/// Wraps a [`futures_core::Stream`] of byte chunks as a handler's response so that [`crate::run_streaming`]
+/// forwards each chunk to the runtime API's streaming response endpoint as it is produced,
+/// rather than buffering the whole body into a single response.
+pub struct StreamResponse<S>(pub S);
+
+/// An error deserializing an invocation's JSON payload into the handler's expected event type.
+/// Carries the JSON path at which deserialization failed, which is far more actionable than
+/// serde's default message when an AWS payload shape drifts from what the handler expects.
+#[derive(Debug)]
+pub struct PayloadError {
+    path: String,
+    source: serde_json::Error,
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to deserialize event payload at `{}`: {}",
+            self.path, self.source
+        )
+    }
+}
+
+impl std::error::Error for PayloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Deserializes `body` as `A`, surfacing a [`PayloadError`] with the JSON path of the failure
+/// rather than just a generic serde message when an AWS event payload doesn't match the
+/// expected shape.
+pub fn deserialize_payload<A: DeserializeOwned>(body: &[u8]) -> Result<A, PayloadError> {
+    serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(body)).map_err(|e| PayloadError {
+        path: e.path().to_string(),
+        source: e.into_inner(),
+    })
+}
+
+/// Error and diagnostic information describing a Lambda function's failed execution,
+/// reported back to the runtime API.
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    /// The type of error that occurred.
+    pub error_type: String,
+    /// A message describing the error that occurred.
+    pub error_message: String,
+}
@@ -0,0 +1,191 @@
+//! Typed models for common AWS event sources, so handlers can write
+//! `async fn(event: S3Event, ctx: Context)` directly instead of hand-rolling structs.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Event sent by S3 for object-created/removed notifications.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Event {
+    /// The individual bucket notifications contained in this event.
+    #[serde(rename = "Records")]
+    pub records: Vec<S3EventRecord>,
+}
+
+/// A single record within an [`S3Event`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3EventRecord {
+    /// The version of the event record format.
+    #[serde(rename = "eventVersion")]
+    pub event_version: String,
+    /// The AWS service the event originated from, e.g. `aws:s3`.
+    #[serde(rename = "eventSource")]
+    pub event_source: String,
+    /// The AWS region where the bucket resides.
+    #[serde(rename = "awsRegion")]
+    pub aws_region: String,
+    /// The time S3 finished processing the request, in ISO-8601 format.
+    #[serde(rename = "eventTime")]
+    pub event_time: String,
+    /// The type of event, e.g. `ObjectCreated:Put`.
+    #[serde(rename = "eventName")]
+    pub event_name: String,
+    /// The bucket and object the event describes.
+    pub s3: S3Entity,
+}
+
+/// The bucket and object an [`S3EventRecord`] describes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Entity {
+    /// The bucket the object belongs to.
+    pub bucket: S3Bucket,
+    /// The object the event describes.
+    pub object: S3Object,
+}
+
+/// The bucket an [`S3Entity`] belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Bucket {
+    /// The name of the bucket.
+    pub name: String,
+}
+
+/// The object an [`S3Entity`] describes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct S3Object {
+    /// The URL-encoded key of the object.
+    pub key: String,
+    /// The size of the object in bytes, if known.
+    #[serde(default)]
+    pub size: Option<i64>,
+}
+
+/// Event sent when an SQS queue configured as an event source delivers messages.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SqsEvent {
+    /// The individual messages delivered in this event.
+    #[serde(rename = "Records")]
+    pub records: Vec<SqsMessage>,
+}
+
+/// A single message within an [`SqsEvent`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SqsMessage {
+    /// The unique ID assigned to the message by SQS.
+    #[serde(rename = "messageId")]
+    pub message_id: String,
+    /// The receipt handle used to delete or modify visibility of the message.
+    #[serde(rename = "receiptHandle")]
+    pub receipt_handle: String,
+    /// The message's payload.
+    pub body: String,
+    /// SQS-provided system attributes, such as `SentTimestamp` or `SenderId`.
+    pub attributes: HashMap<String, String>,
+    /// User-provided message attributes.
+    #[serde(rename = "messageAttributes", default)]
+    pub message_attributes: HashMap<String, serde_json::Value>,
+    /// The AWS service the message originated from, e.g. `aws:sqs`.
+    #[serde(rename = "eventSource")]
+    pub event_source: String,
+    /// The ARN of the queue the message was sent to.
+    #[serde(rename = "eventSourceARN")]
+    pub event_source_arn: String,
+    /// The AWS region the queue resides in.
+    #[serde(rename = "awsRegion")]
+    pub aws_region: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::deserialize_payload;
+
+    const S3_EVENT_JSON: &str = r#"{
+        "Records": [
+            {
+                "eventVersion": "2.1",
+                "eventSource": "aws:s3",
+                "awsRegion": "us-east-1",
+                "eventTime": "2021-01-01T12:00:00.000Z",
+                "eventName": "ObjectCreated:Put",
+                "s3": {
+                    "bucket": { "name": "example-bucket" },
+                    "object": { "key": "test/key.txt", "size": 1024 }
+                }
+            }
+        ]
+    }"#;
+
+    const SQS_EVENT_JSON: &str = r#"{
+        "Records": [
+            {
+                "messageId": "19dd0b57-b21e-4ac1-bd88-01bbb068cb78",
+                "receiptHandle": "MessageReceiptHandle",
+                "body": "Hello from SQS!",
+                "attributes": {
+                    "ApproximateReceiveCount": "1",
+                    "SentTimestamp": "1523232000000",
+                    "SenderId": "123456789012",
+                    "ApproximateFirstReceiveTimestamp": "1523232000001"
+                },
+                "messageAttributes": {},
+                "eventSource": "aws:sqs",
+                "eventSourceARN": "arn:aws:sqs:us-east-1:123456789012:MyQueue",
+                "awsRegion": "us-east-1"
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn deserializes_an_s3_event() {
+        let event: S3Event = deserialize_payload(S3_EVENT_JSON.as_bytes()).unwrap();
+
+        assert_eq!(event.records.len(), 1);
+        let record = &event.records[0];
+        assert_eq!(record.event_name, "ObjectCreated:Put");
+        assert_eq!(record.aws_region, "us-east-1");
+        assert_eq!(record.s3.bucket.name, "example-bucket");
+        assert_eq!(record.s3.object.key, "test/key.txt");
+        assert_eq!(record.s3.object.size, Some(1024));
+    }
+
+    #[test]
+    fn s3_event_round_trips_through_json() {
+        let event: S3Event = deserialize_payload(S3_EVENT_JSON.as_bytes()).unwrap();
+        let json = serde_json::to_vec(&event).unwrap();
+        let reparsed: S3Event = deserialize_payload(&json).unwrap();
+        assert_eq!(event, reparsed);
+    }
+
+    #[test]
+    fn deserializes_an_sqs_event() {
+        let event: SqsEvent = deserialize_payload(SQS_EVENT_JSON.as_bytes()).unwrap();
+
+        assert_eq!(event.records.len(), 1);
+        let message = &event.records[0];
+        assert_eq!(message.message_id, "19dd0b57-b21e-4ac1-bd88-01bbb068cb78");
+        assert_eq!(message.body, "Hello from SQS!");
+        assert_eq!(message.attributes.get("SenderId"), Some(&"123456789012".to_owned()));
+        assert_eq!(message.event_source_arn, "arn:aws:sqs:us-east-1:123456789012:MyQueue");
+    }
+
+    #[test]
+    fn sqs_event_round_trips_through_json() {
+        let event: SqsEvent = deserialize_payload(SQS_EVENT_JSON.as_bytes()).unwrap();
+        let json = serde_json::to_vec(&event).unwrap();
+        let reparsed: SqsEvent = deserialize_payload(&json).unwrap();
+        assert_eq!(event, reparsed);
+    }
+
+    #[test]
+    fn reports_the_json_path_of_a_malformed_field() {
+        let json = SQS_EVENT_JSON.replacen("\"19dd0b57-b21e-4ac1-bd88-01bbb068cb78\"", "42", 1);
+
+        let err = deserialize_payload::<SqsEvent>(json.as_bytes()).unwrap_err();
+
+        assert!(
+            err.to_string().contains("Records[0].messageId"),
+            "expected the JSON path in the error message, got: {}",
+            err
+        );
+    }
+}